@@ -152,10 +152,411 @@ pub fn remove_child<T:Copy,O:WriteMethods<T>>(ops: &O, parent: T, child: T) {
     }
 }
 
+pub fn insert_before<T:Copy,O:WriteMethods<T>>(ops: &O, parent: T, new_child: T, reference_child: T) {
+    fail_unless!(!ops.tree_eq(&new_child, &reference_child));
+    match ops.with_tree_fields(&reference_child, |ref_tf| ref_tf.parent) {
+        None => { fail!(~"reference_child is not a child of parent"); }
+        Some(ref_parent) => { fail_unless!(ops.tree_eq(&parent, &ref_parent)); }
+    }
+
+    do ops.with_tree_fields(&new_child) |new_tf| {
+        match new_tf.parent {
+          Some(_) => { fail!(~"Already has a parent"); }
+          None => { new_tf.parent = Some(parent); }
+        }
+        fail_unless!(new_tf.prev_sibling.is_none());
+        fail_unless!(new_tf.next_sibling.is_none());
+
+        let prev = ops.with_tree_fields(&reference_child, |ref_tf| ref_tf.prev_sibling);
+        new_tf.prev_sibling = prev;
+        new_tf.next_sibling = Some(reference_child);
+
+        do ops.with_tree_fields(&reference_child) |ref_tf| {
+            ref_tf.prev_sibling = Some(new_child);
+        }
+
+        match prev {
+          None => {
+            do ops.with_tree_fields(&parent) |parent_tf| {
+                parent_tf.first_child = Some(new_child);
+            }
+          }
+          Some(p) => {
+            do ops.with_tree_fields(&p) |prev_tf| {
+                prev_tf.next_sibling = Some(new_child);
+            }
+          }
+        }
+    }
+}
+
+pub fn insert_after<T:Copy,O:WriteMethods<T>>(ops: &O, parent: T, new_child: T, reference_child: T) {
+    fail_unless!(!ops.tree_eq(&new_child, &reference_child));
+    match ops.with_tree_fields(&reference_child, |ref_tf| ref_tf.parent) {
+        None => { fail!(~"reference_child is not a child of parent"); }
+        Some(ref_parent) => { fail_unless!(ops.tree_eq(&parent, &ref_parent)); }
+    }
+
+    do ops.with_tree_fields(&new_child) |new_tf| {
+        match new_tf.parent {
+          Some(_) => { fail!(~"Already has a parent"); }
+          None => { new_tf.parent = Some(parent); }
+        }
+        fail_unless!(new_tf.prev_sibling.is_none());
+        fail_unless!(new_tf.next_sibling.is_none());
+
+        let next = ops.with_tree_fields(&reference_child, |ref_tf| ref_tf.next_sibling);
+        new_tf.next_sibling = next;
+        new_tf.prev_sibling = Some(reference_child);
+
+        do ops.with_tree_fields(&reference_child) |ref_tf| {
+            ref_tf.next_sibling = Some(new_child);
+        }
+
+        match next {
+          None => {
+            do ops.with_tree_fields(&parent) |parent_tf| {
+                parent_tf.last_child = Some(new_child);
+            }
+          }
+          Some(n) => {
+            do ops.with_tree_fields(&n) |next_tf| {
+                next_tf.prev_sibling = Some(new_child);
+            }
+          }
+        }
+    }
+}
+
+pub fn prepend_child<T:Copy,O:WriteMethods<T>>(ops: &O, parent: T, child: T) {
+    let first = ops.with_tree_fields(&parent, |parent_tf| parent_tf.first_child);
+    match first {
+        Some(reference) => insert_before(ops, parent, child, reference),
+        None => add_child(ops, parent, child)
+    }
+}
+
+// Detaches `node` from its current parent, if any, then re-inserts it
+// under `new_parent`.
+pub fn move_subtree<T:Copy,O:WriteMethods<T>>(ops: &O, new_parent: T, node: T) {
+    let old_parent = ops.with_tree_fields(&node, |tf| tf.parent);
+    match old_parent {
+        Some(p) => remove_child(ops, p, node),
+        None => {}
+    }
+    add_child(ops, new_parent, node);
+}
+
 pub fn get_parent<T:Copy,O:ReadMethods<T>>(ops: &O, node: &T) -> Option<T> {
     ops.with_tree_fields(node, |tf| tf.parent)
 }
 
+// A slab-backed node store, addressed by small handles instead of
+// `@`-boxes per node. The slab itself is the one `@`-box (shared by every
+// handle into it), so `ReadMethods`/`WriteMethods` don't need to change
+// shape to reach it — same stateless-witness-over-a-boxed-value pattern
+// `dtree`/`@dummy` use in the tests below.
+pub struct NodeId(uint);
+
+enum Slot<D> {
+    Occupied(D, Tree<ArenaNode<D>>),
+    FreeSlot(Option<NodeId>)
+}
+
+pub struct Arena<D> {
+    slots: ~[Slot<D>],
+    free_list: Option<NodeId>
+}
+
+pub struct ArenaNode<D> {
+    arena: @Arena<D>,
+    id: NodeId
+}
+
+pub fn arena<D>() -> @Arena<D> {
+    @Arena { mut slots: ~[], mut free_list: None }
+}
+
+pub impl<D> Arena<D> {
+    fn alloc(@self, data: D) -> ArenaNode<D> {
+        let id = match self.free_list {
+          Some(copy id) => {
+            let NodeId(i) = id;
+            self.free_list = match self.slots[i] {
+              FreeSlot(next) => next,
+              Occupied(*, *) => fail!(~"corrupt arena free list")
+            };
+            self.slots[i] = Occupied(data, empty());
+            id
+          }
+          None => {
+            let id = NodeId(self.slots.len());
+            self.slots.push(Occupied(data, empty()));
+            id
+          }
+        };
+        ArenaNode { arena: self, id: id }
+    }
+
+    fn free(@self, node: ArenaNode<D>) {
+        let NodeId(i) = node.id;
+        self.slots[i] = FreeSlot(self.free_list);
+        self.free_list = Some(node.id);
+    }
+
+    fn get(@self, node: ArenaNode<D>) -> &self/D {
+        let NodeId(i) = node.id;
+        match self.slots[i] {
+          Occupied(ref d, _) => d,
+          FreeSlot(_) => fail!(~"use of a freed NodeId")
+        }
+    }
+
+    fn get_mut(@self, node: ArenaNode<D>) -> &mut D {
+        let NodeId(i) = node.id;
+        match self.slots[i] {
+          Occupied(ref mut d, _) => d,
+          FreeSlot(_) => fail!(~"use of a freed NodeId")
+        }
+    }
+}
+
+enum arena_ops { arena_ops }
+
+impl<D> ReadMethods<ArenaNode<D>> for arena_ops {
+    fn with_tree_fields<R>(node: &ArenaNode<D>, f: &fn(&mut Tree<ArenaNode<D>>) -> R) -> R {
+        let NodeId(i) = node.id;
+        match node.arena.slots[i] {
+          Occupied(_, ref mut tf) => f(tf),
+          FreeSlot(_) => fail!(~"use of a freed NodeId")
+        }
+    }
+}
+
+impl<D> WriteMethods<ArenaNode<D>> for arena_ops {
+    fn with_tree_fields<R>(node: &ArenaNode<D>, f: &fn(&mut Tree<ArenaNode<D>>) -> R) -> R {
+        ReadMethods::with_tree_fields(node, f)
+    }
+    pure fn tree_eq(a: &ArenaNode<D>, b: &ArenaNode<D>) -> bool {
+        let NodeId(x) = a.id;
+        let NodeId(y) = b.id;
+        x == y
+    }
+}
+
+// Explicit-stack depth-first walk of an entire subtree; `enter` returning
+// false terminates early, like `each_child`.
+pub fn each_descendant_enter_leave<T:Copy,O:ReadMethods<T>>(
+        ops: &O, node: &T, enter: &fn(&T) -> bool, leave: &fn(&T)) {
+    if !enter(node) { return; }
+
+    let mut stack: ~[(T, Option<T>)] = ~[(*node, first_child(ops, node))];
+
+    loop {
+        let (top_node, cursor) = match stack.pop() {
+          None => break,
+          Some(frame) => frame
+        };
+        match cursor {
+          None => {
+            leave(&top_node);
+          }
+          Some(c) => {
+            stack.push((top_node, next_sibling(ops, &c)));
+            if !enter(&c) { return; }
+            stack.push((c, first_child(ops, &c)));
+          }
+        }
+    }
+}
+
+pub fn each_descendant_preorder<T:Copy,O:ReadMethods<T>>(ops: &O, node: &T, f: &fn(&T) -> bool) {
+    each_descendant_enter_leave(ops, node, f, |_n| {});
+}
+
+pub fn each_descendant_postorder<T:Copy,O:ReadMethods<T>>(ops: &O, node: &T, f: &fn(&T) -> bool) {
+    let mut should_continue = true;
+    each_descendant_enter_leave(ops, node,
+        |_n| should_continue,
+        |n| { if should_continue { should_continue = f(n); } });
+}
+
+// Document-order serialization, built on `each_descendant_enter_leave`.
+pub enum TraversalScope {
+    IncludeNode,
+    ChildrenOnly
+}
+
+pub trait Serializer<T> {
+    fn open_node(&self, node: &T);
+    fn close_node(&self, node: &T);
+}
+
+pub fn serialize<T:Copy,O:ReadMethods<T>,S:Serializer<T>>(
+        ops: &O, serializer: &S, node: &T, scope: TraversalScope) {
+    match scope {
+      IncludeNode => {
+        each_descendant_enter_leave(ops, node,
+            |n| { serializer.open_node(n); true },
+            |n| serializer.close_node(n));
+      }
+      ChildrenOnly => {
+        for each_child(ops, node) |c| {
+            serialize(ops, serializer, c, IncludeNode);
+            true
+        };
+      }
+    }
+}
+
+// Rerooting fold, in one pass of work proportional to the tree size.
+pub trait Ops<T, Value, Acc> {
+    fn identity(&self) -> Acc;
+    fn proj(&self, value: &Value) -> Acc;
+    fn mul(&self, a: &Acc, b: &Acc) -> Acc;
+    fn finish(&self, acc: &Acc, node: &T) -> Value;
+}
+
+// down[node], plus the already-computed down-results of its direct
+// children, so `up` below never has to re-derive them.
+struct DownResult<T, Value, Acc> {
+    node: T,
+    value: Value,
+    children: ~[DownResult<T, Value, Acc>],
+    children_acc: ~[Acc]
+}
+
+// A DownResult under construction: its own children are finished, but it's
+// still waiting on its remaining siblings via `cursor`, same convention as
+// each_descendant_enter_leave's stack frames.
+struct DownFrame<T, Value, Acc> {
+    node: T,
+    cursor: Option<T>,
+    children: ~[DownResult<T, Value, Acc>],
+    children_acc: ~[Acc]
+}
+
+// Explicit-stack post-order walk, for the same reason
+// each_descendant_enter_leave is one: arbitrarily deep DOM/layout trees
+// must not recurse through the native call stack.
+fn down<T:Copy,Value:Copy,Acc:Copy,R:ReadMethods<T>,F:Ops<T,Value,Acc>>(
+        ops: &R, fops: &F, root: &T) -> DownResult<T,Value,Acc> {
+    let mut stack: ~[DownFrame<T,Value,Acc>] =
+        ~[DownFrame { node: *root, cursor: first_child(ops, root),
+                       children: ~[], children_acc: ~[] }];
+
+    loop {
+        let mut frame = match stack.pop() {
+          None => fail!(~"down: empty stack"),
+          Some(f) => f
+        };
+        match copy frame.cursor {
+          Some(c) => {
+            frame.cursor = next_sibling(ops, &c);
+            stack.push(frame);
+            stack.push(DownFrame { node: c, cursor: first_child(ops, &c),
+                                    children: ~[], children_acc: ~[] });
+          }
+          None => {
+            let mut acc = fops.identity();
+            for uint::range(0, frame.children_acc.len()) |i| {
+                acc = fops.mul(&acc, &frame.children_acc[i]);
+                true
+            };
+            let dr = DownResult { value: fops.finish(&acc, &frame.node), node: frame.node,
+                                   children: frame.children, children_acc: frame.children_acc };
+            match stack.pop() {
+              None => return dr,
+              Some(mut parent) => {
+                let p = fops.proj(&dr.value);
+                parent.children_acc.push(p);
+                parent.children.push(dr);
+                stack.push(parent);
+              }
+            }
+          }
+        }
+    }
+}
+
+fn up<T:Copy,Value:Copy,Acc:Copy,F:Ops<T,Value,Acc>>(
+        fops: &F, dr: &DownResult<T,Value,Acc>, up_acc: Acc, at_each: &fn(&T, Value)) {
+    let mut stack = ~[(dr, up_acc)];
+    loop {
+        let (dr, up_acc) = match stack.pop() {
+          None => break,
+          Some(frame) => frame
+        };
+        let n = dr.children_acc.len();
+
+        // prefix[i] = product of children_acc[0..i), suffix[i] = product of children_acc[n-i..n)
+        let mut prefix: ~[Acc] = ~[fops.identity()];
+        for uint::range(0, n) |i| {
+            prefix.push(fops.mul(&prefix[i], &dr.children_acc[i]));
+            true
+        };
+        let mut suffix: ~[Acc] = ~[fops.identity()];
+        let mut i = n;
+        while i > 0 {
+            i -= 1;
+            suffix.push(fops.mul(&dr.children_acc[i], &suffix[n - i - 1]));
+        }
+
+        let whole = fops.mul(&up_acc, &prefix[n]);
+        at_each(&dr.node, fops.finish(&whole, &dr.node));
+
+        for uint::range(0, n) |i| {
+            let excl = fops.mul(&up_acc, &fops.mul(&prefix[i], &suffix[n - i - 1]));
+            let child_up = fops.proj(&fops.finish(&excl, &dr.node));
+            stack.push((&dr.children[i], child_up));
+            true
+        };
+    }
+}
+
+pub fn fold<T:Copy,Value:Copy,Acc:Copy,R:ReadMethods<T>,F:Ops<T,Value,Acc>>(
+        ops: &R, fops: &F, root: &T, at_each: &fn(&T, Value)) {
+    let dr = down(ops, fops, root);
+    up(fops, &dr, fops.identity(), at_each);
+}
+
+// Structural subtree equality/hashing, with payload comparison supplied
+// by the caller and the recursion (explicit stack) owned by the module.
+pub fn subtree_eq<T:Copy,O:ReadMethods<T>>(
+        ops: &O, a: &T, b: &T, payload_eq: &fn(&T, &T) -> bool) -> bool {
+    if !payload_eq(a, b) { return false; }
+
+    // From here on the stack only ever holds pairs of siblings under a
+    // shared (already-matched) parent, so continuing on to next_sibling
+    // is comparing the right thing: it never escapes `a`/`b`'s own subtrees.
+    let mut stack: ~[(Option<T>, Option<T>)] = ~[(first_child(ops, a), first_child(ops, b))];
+
+    loop {
+        let (ca, cb) = match stack.pop() {
+          None => return true,
+          Some(pair) => pair
+        };
+        match (ca, cb) {
+          (None, None) => { }
+          (Some(na), Some(nb)) => {
+            if !payload_eq(&na, &nb) { return false; }
+            stack.push((next_sibling(ops, &na), next_sibling(ops, &nb)));
+            stack.push((first_child(ops, &na), first_child(ops, &nb)));
+          }
+          _ => { return false; }
+        }
+    }
+}
+
+pub fn subtree_hash<T:Copy,O:ReadMethods<T>>(
+        ops: &O, node: &T, payload_hash: &fn(&T) -> uint) -> uint {
+    let mut h = 0u;
+    each_descendant_enter_leave(ops, node,
+        |n| { h = (h * 31u + 1u) * 31u + payload_hash(n); true },
+        |_n| { h = h * 31u + 2u; });
+    h
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -269,4 +670,250 @@ mod test {
         }
         fail_unless!(i == 0);
     }
+
+    #[test]
+    fn arena_backed_tree_supports_add_and_remove_child() {
+        let a: @Arena<uint> = arena();
+        let p = a.alloc(3u);
+        let c0 = a.alloc(0u);
+        let c1 = a.alloc(1u);
+        let c2 = a.alloc(2u);
+
+        add_child(&arena_ops, p, c0);
+        add_child(&arena_ops, p, c1);
+        add_child(&arena_ops, p, c2);
+
+        let mut seen = ~[];
+        for each_child(&arena_ops, &p) |c| { seen.push(*a.get(*c)); true };
+        fail_unless!(seen == ~[0u, 1u, 2u]);
+
+        remove_child(&arena_ops, p, c1);
+        let mut seen2 = ~[];
+        for each_child(&arena_ops, &p) |c| { seen2.push(*a.get(*c)); true };
+        fail_unless!(seen2 == ~[0u, 2u]);
+    }
+
+    #[test]
+    fn arena_tree_eq_compares_handles_not_pointers() {
+        let a: @Arena<uint> = arena();
+        let n0 = a.alloc(0u);
+        let n1 = a.alloc(1u);
+        fail_unless!(arena_ops.tree_eq(&n0, &n0));
+        fail_unless!(!arena_ops.tree_eq(&n0, &n1));
+    }
+
+    #[test]
+    fn arena_recycles_freed_slots() {
+        let a: @Arena<uint> = arena();
+        let n0 = a.alloc(0u);
+        a.free(n0);
+        let n1 = a.alloc(1u);
+        fail_unless!(arena_ops.tree_eq(&n0, &n1));
+        fail_unless!(*a.get(n1) == 1u);
+    }
+
+    #[test]
+    fn arena_get_mut_allows_in_place_mutation() {
+        let a: @Arena<uint> = arena();
+        let n = a.alloc(0u);
+        *a.get_mut(n) = 42u;
+        fail_unless!(*a.get(n) == 42u);
+    }
+
+    #[test]
+    fn insert_before_splices_into_sibling_chain() {
+        let (p, children) = parent_with_3_children();
+        let n = new_dummy(9u);
+        insert_before(&dtree, p, n, children[1]);
+
+        let mut seen = ~[];
+        for each_child(&dtree, &p) |c| { seen.push(c.value); true };
+        fail_unless!(seen == ~[children[0].value, n.value, children[1].value, children[2].value]);
+    }
+
+    #[test]
+    fn insert_after_splices_into_sibling_chain() {
+        let (p, children) = parent_with_3_children();
+        let n = new_dummy(9u);
+        insert_after(&dtree, p, n, children[1]);
+
+        let mut seen = ~[];
+        for each_child(&dtree, &p) |c| { seen.push(c.value); true };
+        fail_unless!(seen == ~[children[0].value, children[1].value, n.value, children[2].value]);
+    }
+
+    #[test]
+    fn prepend_child_becomes_first_child() {
+        let (p, children) = parent_with_3_children();
+        let n = new_dummy(9u);
+        prepend_child(&dtree, p, n);
+
+        fail_unless!(first_child(&dtree, &p).get().value == n.value);
+        let mut seen = ~[];
+        for each_child(&dtree, &p) |c| { seen.push(c.value); true };
+        fail_unless!(seen == ~[n.value, children[0].value, children[1].value, children[2].value]);
+    }
+
+    #[test]
+    fn move_subtree_relocates_between_parents() {
+        let (p1, children) = parent_with_3_children();
+        let p2 = new_dummy(4u);
+
+        move_subtree(&dtree, p2, children[1]);
+
+        let mut seen1 = ~[];
+        for each_child(&dtree, &p1) |c| { seen1.push(c.value); true };
+        fail_unless!(seen1 == ~[children[0].value, children[2].value]);
+
+        let mut seen2 = ~[];
+        for each_child(&dtree, &p2) |c| { seen2.push(c.value); true };
+        fail_unless!(seen2 == ~[children[1].value]);
+    }
+
+    fn dummy_value_eq(a: &@dummy, b: &@dummy) -> bool { a.value == b.value }
+    fn dummy_value_hash(n: &@dummy) -> uint { n.value }
+
+    #[test]
+    fn subtree_eq_same_shape_and_values() {
+        let (p1, _) = parent_with_3_children();
+        let (p2, _) = parent_with_3_children();
+        fail_unless!(subtree_eq(&dtree, &p1, &p2, dummy_value_eq));
+    }
+
+    #[test]
+    fn subtree_eq_detects_differing_child_count() {
+        let (p1, _) = parent_with_3_children();
+        let p2 = new_dummy(3u);
+        add_child(&dtree, p2, new_dummy(0u));
+        add_child(&dtree, p2, new_dummy(1u));
+        fail_unless!(!subtree_eq(&dtree, &p1, &p2, dummy_value_eq));
+    }
+
+    #[test]
+    fn subtree_eq_detects_differing_payload() {
+        let (p1, _) = parent_with_3_children();
+        let p2 = new_dummy(3u);
+        add_child(&dtree, p2, new_dummy(0u));
+        add_child(&dtree, p2, new_dummy(9u));
+        add_child(&dtree, p2, new_dummy(2u));
+        fail_unless!(!subtree_eq(&dtree, &p1, &p2, dummy_value_eq));
+    }
+
+    #[test]
+    fn subtree_eq_ignores_siblings_of_the_compared_nodes() {
+        let (p, children) = parent_with_3_children();
+        // children[0] and children[2] are both leaves (equal shape/value
+        // would require equal payload, so compare two leaves that match
+        // on payload but whose surrounding siblings differ).
+        let leaf_a = children[0];
+        let leaf_b = new_dummy(leaf_a.value);
+        fail_unless!(subtree_eq(&dtree, &leaf_a, &leaf_b, dummy_value_eq));
+        // sanity: leaf_a's actual next sibling has a different value, so a
+        // sibling-chain comparison (the bug) would have wrongly failed here.
+        fail_unless!(next_sibling(&dtree, &leaf_a).get().value != leaf_b.value);
+    }
+
+    #[test]
+    fn subtree_hash_matches_for_equal_subtrees() {
+        let (p1, _) = parent_with_3_children();
+        let (p2, _) = parent_with_3_children();
+        fail_unless!(subtree_hash(&dtree, &p1, dummy_value_hash) ==
+                     subtree_hash(&dtree, &p2, dummy_value_hash));
+    }
+
+    #[test]
+    fn subtree_hash_differs_for_differing_shape() {
+        let (p1, _) = parent_with_3_children();
+        let p2 = new_dummy(3u);
+        add_child(&dtree, p2, new_dummy(0u));
+        fail_unless!(subtree_hash(&dtree, &p1, dummy_value_hash) !=
+                     subtree_hash(&dtree, &p2, dummy_value_hash));
+    }
+
+    struct trace_serializer { log: ~[~str] }
+
+    impl Serializer<@dummy> for trace_serializer {
+        fn open_node(&self, node: &@dummy) {
+            self.log.push(fmt!("enter:%u", node.value));
+        }
+        fn close_node(&self, node: &@dummy) {
+            self.log.push(fmt!("leave:%u", node.value));
+        }
+    }
+
+    #[test]
+    fn serialize_include_node_visits_in_document_order() {
+        let (p, _) = parent_with_3_children();
+        let s = trace_serializer { mut log: ~[] };
+        serialize(&dtree, &s, &p, IncludeNode);
+        fail_unless!(s.log == ~[~"enter:3", ~"enter:0", ~"leave:0", ~"enter:1",
+                                 ~"leave:1", ~"enter:2", ~"leave:2", ~"leave:3"]);
+    }
+
+    #[test]
+    fn serialize_children_only_skips_start_node() {
+        let (p, _) = parent_with_3_children();
+        let s = trace_serializer { mut log: ~[] };
+        serialize(&dtree, &s, &p, ChildrenOnly);
+        fail_unless!(s.log == ~[~"enter:0", ~"leave:0", ~"enter:1",
+                                 ~"leave:1", ~"enter:2", ~"leave:2"]);
+    }
+
+    #[test]
+    fn descendant_preorder_visits_node_then_children() {
+        let (p, children) = parent_with_3_children();
+        let mut seen = ~[];
+        for each_descendant_preorder(&dtree, &p) |n| {
+            seen.push(n.value);
+            true
+        };
+        fail_unless!(seen == ~[p.value, children[0].value, children[1].value, children[2].value]);
+    }
+
+    #[test]
+    fn descendant_postorder_visits_children_then_node() {
+        let (p, children) = parent_with_3_children();
+        let mut seen = ~[];
+        for each_descendant_postorder(&dtree, &p) |n| {
+            seen.push(n.value);
+            true
+        };
+        fail_unless!(seen == ~[children[0].value, children[1].value, children[2].value, p.value]);
+    }
+
+    #[test]
+    fn descendant_preorder_break_stops_early() {
+        let (p, _) = parent_with_3_children();
+        let mut i = 0u;
+        for each_descendant_preorder(&dtree, &p) |_n| {
+            i += 1u;
+            break;
+        }
+        fail_unless!(i == 1u);
+    }
+
+    // The sum of every node's value over the whole tree does not depend
+    // on which node is treated as the root, so folding with addition
+    // must report the same total at every node.
+    struct sum_ops;
+
+    impl Ops<@dummy, uint, uint> for sum_ops {
+        fn identity(&self) -> uint { 0u }
+        fn proj(&self, value: &uint) -> uint { *value }
+        fn mul(&self, a: &uint, b: &uint) -> uint { *a + *b }
+        fn finish(&self, acc: &uint, node: &@dummy) -> uint { *acc + node.value }
+    }
+
+    #[test]
+    fn fold_all_roots_agree_on_total() {
+        let (p, children) = parent_with_3_children();
+        let total = p.value + children[0].value + children[1].value + children[2].value;
+
+        let mut seen = 0u;
+        for fold(&dtree, &sum_ops, &p) |_node, value| {
+            fail_unless!(value == total);
+            seen += 1u;
+        };
+        fail_unless!(seen == 4u);
+    }
 }